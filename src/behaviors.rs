@@ -14,6 +14,7 @@
 //!
 //! [`Context`]: crate::engine::Context
 
+use crate::color_math::{lerp, scale};
 use crate::engine::Context;
 use crate::LossyIntoF32;
 use groundhog::RollingTimer;
@@ -23,7 +24,7 @@ use smart_leds::RGB8;
 /// StayColor - A solid constant color
 ///
 /// This is the simplest behavior
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct StayColor;
 
 impl StayColor {
@@ -49,17 +50,15 @@ impl StayColor {
 ///
 /// A cycler can either "start low" as a sine wave, or
 /// "start high", as a cosine wave.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Cycler {
-    func: fn(f32) -> f32,
+    start_high: bool,
 }
 
 impl Cycler {
     /// Create a new cycler starting low, using a sine function
     pub fn new() -> Self {
-        Self {
-            func: <f32 as F32Ext>::sin,
-        }
+        Self { start_high: false }
     }
 
     pub(crate) fn poll<R>(&self, context: &Context<R>) -> Option<RGB8>
@@ -80,26 +79,28 @@ impl Cycler {
         let deltaf = delta.wrapping_add(context.phase_offset_ms).lossy_into();
         let normalized = deltaf / period;
         let rad_norm = normalized * 2.0 * core::f32::consts::PI;
-        let out_norm = (self.func)(rad_norm);
-        let abs_out = out_norm.abs();
-
-        let retval = RGB8 {
-            r: (abs_out * (context.color.r as f32)) as u8,
-            g: (abs_out * (context.color.g as f32)) as u8,
-            b: (abs_out * (context.color.b as f32)) as u8,
+        let out_norm = if self.start_high {
+            F32Ext::cos(rad_norm)
+        } else {
+            F32Ext::sin(rad_norm)
         };
+        let abs_out = out_norm.abs();
 
-        Some(retval)
+        Some(scale(context.color, (abs_out * 255.0) as u8))
     }
 
     /// Start the Cycler high, e.g. using a cosine function
     pub fn start_high(&mut self) {
-        self.func = <f32 as F32Ext>::cos
+        self.start_high = true;
     }
 
     /// Start the Cycler low, e.g. using a sine function
     pub fn start_low(&mut self) {
-        self.func = <f32 as F32Ext>::sin
+        self.start_high = false;
+    }
+
+    pub(crate) fn starts_high(&self) -> bool {
+        self.start_high
     }
 }
 
@@ -107,7 +108,7 @@ impl Cycler {
 ///
 /// This behavior linearly fades all r/g/b channels from the
 /// previous color to the new color
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct SeekColor;
 
 impl SeekColor {
@@ -127,19 +128,13 @@ impl SeekColor {
             return None;
         }
 
-        let delta_r = ((context.color.r as i16) - (context.last_color.r as i16)) as f32;
-        let delta_g = ((context.color.g as i16) - (context.last_color.g as i16)) as f32;
-        let delta_b = ((context.color.b as i16) - (context.last_color.b as i16)) as f32;
         let norm_dt = (delta as f32) / (context.duration_ms as f32);
-        let norm_r = ((context.last_color.r as i16) + ((delta_r * norm_dt) as i16)) as u8;
-        let norm_g = ((context.last_color.g as i16) + ((delta_g * norm_dt) as i16)) as u8;
-        let norm_b = ((context.last_color.b as i16) + ((delta_b * norm_dt) as i16)) as u8;
-
-        Some(RGB8 {
-            r: norm_r,
-            g: norm_g,
-            b: norm_b,
-        })
+
+        Some(lerp(
+            context.last_color,
+            context.color,
+            (norm_dt * 255.0) as u8,
+        ))
     }
 }
 
@@ -148,7 +143,7 @@ impl SeekColor {
 /// FadeColor is similar to a [`Cycler`](Cycler), but is intended for
 /// cases when you don't want a repeating sinusoid, but rather just
 /// want to "fade in" or "fade out" then hold a color.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct FadeColor {
     cycler: Cycler,
 }
@@ -192,4 +187,8 @@ impl FadeColor {
     {
         self.cycler.poll(context)
     }
+
+    pub(crate) fn is_fade_up(&self) -> bool {
+        !self.cycler.starts_high()
+    }
 }
@@ -0,0 +1,522 @@
+//!
+//! A choreography describes the behavior of MANY LEDs over time,
+//! as a list of [`Row`]s. Each row applies a script of [`Action`]s
+//! to a [`Target`] (a single LED, a range of LEDs, or a named
+//! [`Zone`]), starting at some offset from the start of the show.
+//!
+//! A choreography is then compiled into one [`Sequence`] per LED,
+//! using [`compile()`]. Between (and after) rows, LEDs are held at
+//! black, so that every LED stays in sync with the rest of the show,
+//! even when the show is looped.
+//!
+//! Rows are usually written with the [`choreography!()`] macro.
+//!
+//! [`Row`]: crate::choreography::Row
+//! [`Target`]: crate::choreography::Target
+//! [`compile()`]: crate::choreography::compile
+//! [`Action`]: crate::engine::Action
+//! [`Sequence`]: crate::engine::Sequence
+//! [`Zone`]: crate::zones::Zone
+//! [`choreography!()`]: macro@crate::choreography
+
+use crate::engine::{max_duration_ms, Action, LoopBehavior, Sequence};
+use crate::zones::Zones;
+use groundhog::RollingTimer;
+use heapless::Vec;
+use smart_leds::colors::{BLACK, BLUE, LIME, RED, WHITE};
+use smart_leds::RGB8;
+
+/// The LEDs that a [`Row`] applies to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target<'a> {
+    /// A single LED
+    Led(usize),
+
+    /// A contiguous range of LEDs, from `start` up to (but
+    /// not including) `end`
+    Range {
+        /// The first LED of the range
+        start: usize,
+
+        /// One past the last LED of the range
+        end: usize,
+    },
+
+    /// A named [`Zone`](crate::zones::Zone)
+    Zone(&'a str),
+}
+
+impl<'a> Target<'a> {
+    /// Target a single LED
+    pub const fn led(index: usize) -> Self {
+        Target::Led(index)
+    }
+
+    /// Target a contiguous range of LEDs, from `start` up to
+    /// (but not including) `end`
+    pub const fn range(start: usize, end: usize) -> Self {
+        Target::Range { start, end }
+    }
+
+    /// Target a named zone
+    pub const fn zone(name: &'a str) -> Self {
+        Target::Zone(name)
+    }
+
+    fn contains(&self, zones: &Zones<'_>, index: usize) -> bool {
+        match *self {
+            Target::Led(led) => led == index,
+            Target::Range { start, end } => (start..end).contains(&index),
+            Target::Zone(name) => zones.get(name).map(|z| z.contains(index)).unwrap_or(false),
+        }
+    }
+}
+
+/// A single row of a choreography
+///
+/// A Row plays a script of [`Action`]s on all LEDs of its [`Target`],
+/// starting `start_ms` milliseconds after the start of the show.
+///
+/// [`Action`]: crate::engine::Action
+pub struct Row<'a, R> {
+    target: Target<'a>,
+    start_ms: u32,
+    actions: &'a [Action<R>],
+}
+
+impl<'a, R> Row<'a, R> {
+    /// Create a new row
+    pub const fn new(target: Target<'a>, start_ms: u32, actions: &'a [Action<R>]) -> Self {
+        Self {
+            target,
+            start_ms,
+            actions,
+        }
+    }
+
+    /// The LEDs this row applies to
+    pub fn target(&self) -> Target<'a> {
+        self.target
+    }
+
+    /// The start time of this row, in milliseconds from the start of the show
+    pub fn start_ms(&self) -> u32 {
+        self.start_ms
+    }
+
+    /// The script played by this row
+    pub fn actions(&self) -> &'a [Action<R>] {
+        self.actions
+    }
+}
+
+/// An error encountered while compiling a choreography
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompileError {
+    /// A row targets a zone that does not exist
+    UnknownZone {
+        /// The index of the row
+        row: usize,
+    },
+
+    /// A row starts before the previous row for the same LED
+    /// has finished, or after a previous row that plays forever
+    Overlap {
+        /// The index of the row
+        row: usize,
+
+        /// The LED that both rows apply to
+        led: usize,
+    },
+
+    /// An LED needs more actions than fit in its [`Sequence`]
+    ///
+    /// [`Sequence`]: crate::engine::Sequence
+    TooLong {
+        /// The LED that does not fit
+        led: usize,
+    },
+}
+
+/// Compile a choreography into one [`Sequence`] per LED
+///
+/// Rows that apply to the same LED must be listed in the order they
+/// play, and may not overlap in time. Any gaps between rows, as well
+/// as the time after an LED's last row until the end of the show,
+/// are filled by holding the LED at black. LEDs that are not
+/// targeted by any row are cleared.
+///
+/// Holds that are too long for the timer to measure are split into
+/// several actions, which all count towards the capacity of the
+/// sequence.
+///
+/// All sequences use the same `behavior`, e.g. to loop the
+/// entire show.
+///
+/// If an error is returned, none of the sequences are modified.
+///
+/// [`Sequence`]: crate::engine::Sequence
+pub fn compile<R, const N: usize>(
+    rows: &[Row<'_, R>],
+    zones: &Zones<'_>,
+    leds: &mut [Sequence<R, N>],
+    behavior: LoopBehavior,
+) -> Result<(), CompileError>
+where
+    R: RollingTimer<Tick = u32> + Default + Clone,
+{
+    for (i, row) in rows.iter().enumerate() {
+        if let Target::Zone(name) = row.target {
+            if zones.get(name).is_none() {
+                return Err(CompileError::UnknownZone { row: i });
+            }
+        }
+    }
+
+    // Figure out how long the show is, so shorter LEDs can be padded
+    // to stay in sync. `None` means at least one LED plays forever.
+    let mut show_end = Some(0);
+    for led in 0..leds.len() {
+        show_end = match (show_end, plan_led(rows, zones, led, |_| {})?) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            _ => None,
+        };
+    }
+
+    // Make sure every LED fits, before modifying any of them
+    for led in 0..leds.len() {
+        let mut count = 0;
+        let end = plan_led(rows, zones, led, |_| count += 1)?;
+        if count != 0 {
+            if let Some(gap) = padding(end, show_end) {
+                fill_black::<R, _>(gap, |_| count += 1);
+            }
+        }
+        if count > N {
+            return Err(CompileError::TooLong { led });
+        }
+    }
+
+    for (led, seq) in leds.iter_mut().enumerate() {
+        let mut actions: Vec<Action<R>, N> = Vec::new();
+        let end = plan_led(rows, zones, led, |a| {
+            actions.push(a).ok();
+        })?;
+
+        if actions.is_empty() {
+            seq.clear();
+            continue;
+        }
+
+        if let Some(gap) = padding(end, show_end) {
+            fill_black(gap, |a| {
+                actions.push(a).ok();
+            });
+        }
+
+        seq.set(&actions, behavior.clone());
+    }
+
+    Ok(())
+}
+
+/// The amount of time an LED that finishes at `end` must be held at
+/// black to finish with the rest of the show, if any
+fn padding(end: Option<u32>, show_end: Option<u32>) -> Option<u32> {
+    match (end, show_end) {
+        (Some(end), Some(show_end)) if end < show_end => Some(show_end - end),
+        _ => None,
+    }
+}
+
+/// Walk all rows that apply to the given LED, passing each action
+/// (including any black gaps) to `f`. Returns the time at which the
+/// LED's last row ends, or `None` if it plays forever.
+fn plan_led<R, F>(
+    rows: &[Row<'_, R>],
+    zones: &Zones<'_>,
+    led: usize,
+    mut f: F,
+) -> Result<Option<u32>, CompileError>
+where
+    R: RollingTimer<Tick = u32> + Default + Clone,
+    F: FnMut(Action<R>),
+{
+    let mut cursor = Some(0u32);
+
+    for (i, row) in rows.iter().enumerate() {
+        if !row.target.contains(zones, led) {
+            continue;
+        }
+
+        let now = match cursor {
+            Some(now) if now <= row.start_ms => now,
+            _ => return Err(CompileError::Overlap { row: i, led }),
+        };
+
+        if row.start_ms > now {
+            fill_black(row.start_ms - now, &mut f);
+        }
+
+        cursor = Some(row.start_ms);
+        for action in row.actions {
+            cursor = match (cursor, action.play_time().as_ms()) {
+                (Some(c), Some(t)) => Some(c.saturating_add(t.min(u32::MAX as u64) as u32)),
+                _ => None,
+            };
+            f(action.clone());
+        }
+    }
+
+    Ok(cursor)
+}
+
+/// Hold an LED at black for `duration_ms`, passing each action to `f`
+///
+/// Long holds are split into as many actions as needed for the timer
+/// to be able to measure each of them.
+fn fill_black<R, F>(duration_ms: u32, mut f: F)
+where
+    R: RollingTimer<Tick = u32> + Default + Clone,
+    F: FnMut(Action<R>),
+{
+    let max_ms = max_duration_ms::<R>();
+    let mut remaining = duration_ms;
+    while remaining > 0 {
+        let chunk = remaining.min(max_ms);
+        f(hold_black(chunk));
+        remaining -= chunk;
+    }
+}
+
+fn hold_black<R>(duration_ms: u32) -> Action<R>
+where
+    R: RollingTimer<Tick = u32> + Default + Clone,
+{
+    hold(BLACK, duration_ms)
+}
+
+fn hold<R>(color: RGB8, duration_ms: u32) -> Action<R>
+where
+    R: RollingTimer<Tick = u32> + Default + Clone,
+{
+    Action::build()
+        .solid()
+        .color(color)
+        .for_ms(duration_ms)
+        .once()
+        .finish()
+}
+
+/// The largest number of actions [`self_test()`] needs for a single LED
+pub const SELF_TEST_ACTIONS: usize = 8;
+
+/// Fill the sequences with a self-test pattern, for spotting dead
+/// LEDs and swapped color channels
+///
+/// One after the other, each LED shows red, green, blue, and then
+/// white, for `step_ms` each. Afterwards, a single white dot sweeps
+/// along the whole strip, staying on each LED for `step_ms`. All
+/// other LEDs are black.
+///
+/// Sequences must have room for up to [`SELF_TEST_ACTIONS`] actions.
+/// If an error is returned, none of the sequences are modified.
+///
+/// # Example
+///
+/// ```rust
+/// use choreographer::{
+///     choreography::{self_test, SELF_TEST_ACTIONS},
+///     engine::{LoopBehavior, Sequence},
+/// };
+/// # use groundhog::std_timer::Timer;
+/// # type MicroTimer = Timer<1_000_000>;
+///
+/// let mut leds: [Sequence<MicroTimer, SELF_TEST_ACTIONS>; 16] = Sequence::new_array();
+/// self_test(&mut leds, 250, LoopBehavior::LoopForever).unwrap();
+/// ```
+pub fn self_test<R, const N: usize>(
+    leds: &mut [Sequence<R, N>],
+    step_ms: u32,
+    behavior: LoopBehavior,
+) -> Result<(), CompileError>
+where
+    R: RollingTimer<Tick = u32> + Default + Clone,
+{
+    let count = leds.len();
+
+    for led in 0..count {
+        if self_test_led::<R>(led, count, step_ms).len() > N {
+            return Err(CompileError::TooLong { led });
+        }
+    }
+
+    for (led, seq) in leds.iter_mut().enumerate() {
+        seq.set(&self_test_led(led, count, step_ms), behavior.clone());
+    }
+
+    Ok(())
+}
+
+fn self_test_led<R>(led: usize, count: usize, step_ms: u32) -> Vec<Action<R>, SELF_TEST_ACTIONS>
+where
+    R: RollingTimer<Tick = u32> + Default + Clone,
+{
+    let steps = |n: usize| (n.min(u32::MAX as usize) as u32).saturating_mul(step_ms);
+    let after = count - led - 1;
+
+    let mut actions = Vec::new();
+    let mut push = |a: Action<R>| {
+        if a.duration_ms != 0 {
+            actions.push(a).ok();
+        }
+    };
+
+    // Channel test, four steps per LED
+    push(hold_black(steps(led).saturating_mul(4)));
+    for color in [RED, LIME, BLUE, WHITE].iter() {
+        push(hold(*color, step_ms));
+    }
+
+    // Wait for the channel test of the remaining LEDs, and the
+    // sweep of the previous LEDs
+    push(hold_black(
+        steps(after).saturating_mul(4).saturating_add(steps(led)),
+    ));
+
+    // Sweep
+    push(hold(WHITE, step_ms));
+    push(hold_black(steps(after)));
+
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compile, self_test, CompileError, Row, Target};
+    use crate::{
+        colors::WHITE,
+        engine::{Action, LoopBehavior, Sequence},
+        script,
+        validate::validate,
+        zones::{Zone, Zones},
+    };
+    use groundhog::std_timer::Timer;
+
+    type MicroTimer = Timer<1_000_000>;
+
+    const ZONES: Zones = Zones::new(&[Zone::range("left", 0, 2), Zone::range("right", 2, 4)]);
+
+    #[test]
+    fn compile_rows() {
+        let blink = script! {
+            | action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+            |  solid |  WHITE |         100 |         0.0 |               0 |   once |
+            |  solid |  BLACK |         100 |         0.0 |               0 |   once |
+        };
+        let pulse = script! {
+            | action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+            |    sin |    RED |        1000 |      1000.0 |               0 |   once |
+        };
+
+        let mut leds: [Sequence<MicroTimer, 4>; 5] = Sequence::new_array();
+
+        let rows = [
+            Row::new(Target::zone("left"), 0, &blink),
+            Row::new(Target::zone("right"), 500, &pulse),
+            Row::new(Target::led(0), 200, &pulse),
+        ];
+        assert_eq!(
+            compile(&rows, &ZONES, &mut leds, LoopBehavior::LoopForever),
+            Ok(())
+        );
+
+        // LED 0 needs 4 actions: blink(2) + pulse + padding, which
+        // no longer fits with a gap in front of the pulse
+        let rows = [
+            Row::new(Target::zone("left"), 0, &blink),
+            Row::new(Target::led(0), 300, &pulse),
+            Row::new(Target::zone("right"), 0, &pulse),
+            Row::new(Target::zone("right"), 2000, &pulse),
+        ];
+        assert_eq!(
+            compile(&rows, &ZONES, &mut leds, LoopBehavior::OneShot),
+            Err(CompileError::TooLong { led: 0 })
+        );
+
+        let rows = [
+            Row::new(Target::range(0, 4), 0, &pulse),
+            Row::new(Target::zone("right"), 500, &blink),
+        ];
+        assert_eq!(
+            compile(&rows, &ZONES, &mut leds, LoopBehavior::OneShot),
+            Err(CompileError::Overlap { row: 1, led: 2 })
+        );
+
+        let rows = [Row::new(Target::zone("middle"), 0, &pulse)];
+        assert_eq!(
+            compile(&rows, &ZONES, &mut leds, LoopBehavior::OneShot),
+            Err(CompileError::UnknownZone { row: 0 })
+        );
+    }
+
+    #[test]
+    fn long_holds() {
+        let long = [Action::build()
+            .solid()
+            .color(WHITE)
+            .for_ms(5000)
+            .times(1000)
+            .finish()];
+        let short = [Action::build()
+            .solid()
+            .color(WHITE)
+            .for_ms(1)
+            .once()
+            .finish()];
+
+        let rows = [
+            Row::new(Target::led(0), 0, &long),
+            Row::new(Target::led(1), 0, &short),
+        ];
+
+        // The padding of LED 1 is too long to measure with a
+        // microsecond timer, so it is split into three holds
+        let mut leds: [Sequence<MicroTimer, 4>; 2] = Sequence::new_array();
+        assert_eq!(
+            compile(&rows, &ZONES, &mut leds, LoopBehavior::OneShot),
+            Ok(())
+        );
+        assert_eq!(leds[1].actions().len(), 4);
+        for led in leds.iter() {
+            assert_eq!(led.play_time().as_ms(), Some(5_005_000));
+            assert_eq!(validate::<_, 4>(led.actions()), Ok(()));
+        }
+
+        let mut leds: [Sequence<MicroTimer, 3>; 2] = Sequence::new_array();
+        assert_eq!(
+            compile(&rows, &ZONES, &mut leds, LoopBehavior::OneShot),
+            Err(CompileError::TooLong { led: 1 })
+        );
+    }
+
+    #[test]
+    fn self_test_fits() {
+        let mut leds: [Sequence<MicroTimer, 8>; 3] = Sequence::new_array();
+        assert_eq!(self_test(&mut leds, 100, LoopBehavior::OneShot), Ok(()));
+
+        // Every LED plays for the same amount of time
+        for led in leds.iter() {
+            assert_eq!(led.play_time().as_ms(), Some(1500));
+        }
+        assert_eq!(leds[0].actions().len(), 7);
+        assert_eq!(leds[1].actions().len(), 8);
+        assert_eq!(leds[2].actions().len(), 7);
+
+        let mut leds: [Sequence<MicroTimer, 7>; 3] = Sequence::new_array();
+        assert_eq!(
+            self_test(&mut leds, 100, LoopBehavior::OneShot),
+            Err(CompileError::TooLong { led: 1 })
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//!
+//! Small, overflow-free helpers for doing math on [`RGB8`] colors.
+//!
+//! All operations are performed per-channel, and never wrap: results
+//! that would leave the range of a `u8` are saturated instead.
+//!
+//! These are the same operations used by the built in [behaviors],
+//! and are intended to be used when writing custom effects as well.
+//!
+//! [`RGB8`]: crate::RGB8
+//! [behaviors]: crate::behaviors
+
+use smart_leds::RGB8;
+
+/// Add two colors, saturating each channel at 255
+pub fn saturating_add(a: RGB8, b: RGB8) -> RGB8 {
+    RGB8 {
+        r: a.r.saturating_add(b.r),
+        g: a.g.saturating_add(b.g),
+        b: a.b.saturating_add(b.b),
+    }
+}
+
+/// Subtract color `b` from color `a`, saturating each channel at 0
+pub fn saturating_sub(a: RGB8, b: RGB8) -> RGB8 {
+    RGB8 {
+        r: a.r.saturating_sub(b.r),
+        g: a.g.saturating_sub(b.g),
+        b: a.b.saturating_sub(b.b),
+    }
+}
+
+/// Scale a single channel by `scale / 255`, rounding to the nearest value
+pub fn scale_channel(channel: u8, scale: u8) -> u8 {
+    (((channel as u16) * (scale as u16) + 127) / 255) as u8
+}
+
+/// Scale a color by `scale / 255`
+///
+/// A `scale` of 255 leaves the color unchanged, and a `scale`
+/// of 0 results in black.
+pub fn scale(color: RGB8, scale: u8) -> RGB8 {
+    RGB8 {
+        r: scale_channel(color.r, scale),
+        g: scale_channel(color.g, scale),
+        b: scale_channel(color.b, scale),
+    }
+}
+
+/// Linearly interpolate between two colors
+///
+/// A `t` of 0 results in `from`, and a `t` of 255 results in `to`.
+pub fn lerp(from: RGB8, to: RGB8, t: u8) -> RGB8 {
+    fn lerp_channel(from: u8, to: u8, t: u8) -> u8 {
+        let delta = (to as i32) - (from as i32);
+        ((from as i32) + ((delta * (t as i32)) / 255)) as u8
+    }
+
+    RGB8 {
+        r: lerp_channel(from.r, to.r, t),
+        g: lerp_channel(from.g, to.g, t),
+        b: lerp_channel(from.b, to.b, t),
+    }
+}
+
+/// The per-channel maximum of two colors
+pub fn max(a: RGB8, b: RGB8) -> RGB8 {
+    RGB8 {
+        r: a.r.max(b.r),
+        g: a.g.max(b.g),
+        b: a.b.max(b.b),
+    }
+}
+
+/// The per-channel minimum of two colors
+pub fn min(a: RGB8, b: RGB8) -> RGB8 {
+    RGB8 {
+        r: a.r.min(b.r),
+        g: a.g.min(b.g),
+        b: a.b.min(b.b),
+    }
+}
+
+/// Multiply two colors, e.g. to darken or tint one color by another
+///
+/// Multiplying by white leaves a color unchanged, and multiplying
+/// by black results in black.
+pub fn multiply(a: RGB8, b: RGB8) -> RGB8 {
+    RGB8 {
+        r: scale_channel(a.r, b.r),
+        g: scale_channel(a.g, b.g),
+        b: scale_channel(a.b, b.b),
+    }
+}
+
+/// Screen two colors, the inverse of [`multiply`]
+///
+/// The result is always at least as bright as either input.
+pub fn screen(a: RGB8, b: RGB8) -> RGB8 {
+    fn screen_channel(a: u8, b: u8) -> u8 {
+        255 - scale_channel(255 - a, 255 - b)
+    }
+
+    RGB8 {
+        r: screen_channel(a.r, b.r),
+        g: screen_channel(a.g, b.g),
+        b: screen_channel(a.b, b.b),
+    }
+}
+
+/// A method of combining one color on top of another
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlendMode {
+    /// The top color replaces the bottom color
+    #[default]
+    Replace,
+
+    /// The colors are added, saturating at full brightness.
+    ///
+    /// This is useful for "sparkles" over an ambient background.
+    Add,
+
+    /// The colors are multiplied, see [`multiply`]
+    Multiply,
+
+    /// The colors are screened, see [`screen`]
+    Screen,
+
+    /// The brightest value of each channel is used
+    Max,
+}
+
+/// Blend the `top` color over the `bottom` color
+///
+/// The result of the blend is then mixed with `bottom` according to
+/// `alpha`, where 255 uses the blended color, and 0 leaves `bottom`
+/// unchanged.
+pub fn blend(bottom: RGB8, top: RGB8, mode: BlendMode, alpha: u8) -> RGB8 {
+    let blended = match mode {
+        BlendMode::Replace => top,
+        BlendMode::Add => saturating_add(bottom, top),
+        BlendMode::Multiply => multiply(bottom, top),
+        BlendMode::Screen => screen(bottom, top),
+        BlendMode::Max => max(bottom, top),
+    };
+
+    lerp(bottom, blended, alpha)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: RGB8 = RGB8 {
+        r: 200,
+        g: 100,
+        b: 0,
+    };
+    const B: RGB8 = RGB8 {
+        r: 100,
+        g: 200,
+        b: 50,
+    };
+
+    #[test]
+    fn saturating() {
+        assert_eq!(
+            saturating_add(A, B),
+            RGB8 {
+                r: 255,
+                g: 255,
+                b: 50
+            }
+        );
+        assert_eq!(saturating_sub(A, B), RGB8 { r: 100, g: 0, b: 0 });
+    }
+
+    #[test]
+    fn scaling() {
+        assert_eq!(scale(A, 255), A);
+        assert_eq!(scale(A, 0), RGB8::default());
+        assert_eq!(
+            scale(A, 128),
+            RGB8 {
+                r: 100,
+                g: 50,
+                b: 0
+            }
+        );
+    }
+
+    #[test]
+    fn interpolation() {
+        assert_eq!(lerp(A, B, 0), A);
+        assert_eq!(lerp(A, B, 255), B);
+        assert_eq!(
+            lerp(A, B, 51),
+            RGB8 {
+                r: 180,
+                g: 120,
+                b: 10
+            }
+        );
+    }
+
+    #[test]
+    fn blending() {
+        let black = RGB8::default();
+        let white = RGB8 {
+            r: 255,
+            g: 255,
+            b: 255,
+        };
+
+        assert_eq!(blend(A, B, BlendMode::Replace, 255), B);
+        assert_eq!(blend(A, B, BlendMode::Replace, 0), A);
+        assert_eq!(blend(A, B, BlendMode::Add, 255), saturating_add(A, B));
+        assert_eq!(blend(A, white, BlendMode::Multiply, 255), A);
+        assert_eq!(blend(A, black, BlendMode::Multiply, 255), black);
+        assert_eq!(blend(A, black, BlendMode::Screen, 255), A);
+        assert_eq!(blend(A, white, BlendMode::Screen, 255), white);
+        assert_eq!(blend(A, B, BlendMode::Max, 255), max(A, B));
+    }
+}
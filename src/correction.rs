@@ -0,0 +1,476 @@
+//!
+//! Output correction is applied to colors AFTER they have
+//! been produced by a [`Sequence`], right before they are sent
+//! to the physical LEDs.
+//!
+//! This allows scripts to be written once, without knowing
+//! anything about the specific LEDs or diffusers they will
+//! be shown on. Calibration for a given batch of LEDs is then
+//! done here, at the output stage.
+//!
+//! All correction is done in fixed point, to avoid the need for
+//! floating point math on every LED of every frame.
+//!
+//! [`Sequence`]: crate::engine::Sequence
+
+use crate::color_math::scale;
+use micromath::F32Ext;
+use smart_leds::RGB8;
+
+/// A 3x3 color correction matrix
+///
+/// Each output channel is calculated as a weighted sum of
+/// the three input channels:
+///
+/// ```text
+/// | r' |   | rr rg rb |   | r |
+/// | g' | = | gr gg gb | * | g |
+/// | b' |   | br bg bb |   | b |
+/// ```
+///
+/// Coefficients are stored as signed 8.8 fixed point numbers,
+/// meaning that [`ColorMatrix::ONE`] (256) represents a gain of
+/// 1.0. Results are rounded, and clamped to the range of a `u8`
+/// according to the matrix's [`ClampMode`].
+///
+/// A matrix can be applied to a single color, or to any slice of
+/// colors, e.g. all LEDs of a strip, or only the LEDs of one zone.
+///
+/// # Example
+///
+/// Reducing the blue channel of LEDs behind a blue-tinted diffuser:
+///
+/// ```rust
+/// use choreographer::{correction::ColorMatrix, RGB8};
+///
+/// let matrix = ColorMatrix::from_f32([
+///     [1.0, 0.0, 0.0],
+///     [0.0, 1.0, 0.0],
+///     [0.0, 0.0, 0.75],
+/// ]);
+///
+/// let mut frame = [RGB8 { r: 200, g: 200, b: 200 }; 4];
+/// matrix.apply_frame(&mut frame);
+/// assert_eq!(frame[0], RGB8 { r: 200, g: 200, b: 150 });
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorMatrix {
+    coeffs: [[i16; 3]; 3],
+    clamp: ClampMode,
+}
+
+impl ColorMatrix {
+    /// The fixed point representation of a gain of 1.0
+    pub const ONE: i16 = 256;
+
+    /// The identity matrix, which does not change any colors
+    pub const IDENTITY: ColorMatrix =
+        ColorMatrix::new([[Self::ONE, 0, 0], [0, Self::ONE, 0], [0, 0, Self::ONE]]);
+
+    /// Create a new matrix from 8.8 fixed point coefficients
+    ///
+    /// `coeffs[out][in]` is the contribution of input channel `in`
+    /// to output channel `out`, in r, g, b order.
+    ///
+    /// The matrix will use [`ClampMode::Saturate`], see
+    /// [`ColorMatrix::with_clamp()`] to change this.
+    pub const fn new(coeffs: [[i16; 3]; 3]) -> Self {
+        Self {
+            coeffs,
+            clamp: ClampMode::Saturate,
+        }
+    }
+
+    /// Set how output channels that exceed the range of a `u8` are handled
+    pub const fn with_clamp(mut self, clamp: ClampMode) -> Self {
+        self.clamp = clamp;
+        self
+    }
+
+    /// Create a new matrix from floating point coefficients
+    ///
+    /// Coefficients are converted to 8.8 fixed point, and will
+    /// saturate outside of the range of roughly -128.0..128.0
+    pub fn from_f32(coeffs: [[f32; 3]; 3]) -> Self {
+        let mut fixed = [[0i16; 3]; 3];
+        for (frow, row) in fixed.iter_mut().zip(coeffs.iter()) {
+            for (fc, c) in frow.iter_mut().zip(row.iter()) {
+                *fc = F32Ext::round(c * (Self::ONE as f32)) as i16;
+            }
+        }
+        Self::new(fixed)
+    }
+
+    /// Create a matrix that only scales each channel individually
+    pub const fn from_gains(r: i16, g: i16, b: i16) -> Self {
+        Self::new([[r, 0, 0], [0, g, 0], [0, 0, b]])
+    }
+
+    /// Obtain the fixed point coefficients of this matrix
+    pub const fn coeffs(&self) -> [[i16; 3]; 3] {
+        self.coeffs
+    }
+
+    /// Apply the matrix to a single color
+    pub fn apply(&self, color: RGB8) -> RGB8 {
+        let mut out = self.mix(color);
+
+        // Round to nearest, then drop the fractional bits
+        for o in out.iter_mut() {
+            *o = (*o + ((Self::ONE as i32) / 2)) >> 8;
+        }
+
+        self.clamp.clamp(out)
+    }
+
+    /// Apply the matrix to a single color, keeping the fractional
+    /// bits of the result, as 8.8 fixed point
+    fn mix(&self, color: RGB8) -> [i32; 3] {
+        let input = [color.r as i32, color.g as i32, color.b as i32];
+        let mut out = [0i32; 3];
+
+        for (o, row) in out.iter_mut().zip(self.coeffs.iter()) {
+            *o = row
+                .iter()
+                .zip(input.iter())
+                .map(|(c, i)| (*c as i32) * i)
+                .sum();
+        }
+
+        out
+    }
+
+    /// Apply the matrix to every color in the given frame
+    pub fn apply_frame(&self, frame: &mut [RGB8]) {
+        frame.iter_mut().for_each(|c| *c = self.apply(*c));
+    }
+
+    /// Apply the matrix to every color of a frame, spreading the
+    /// rounding error across neighboring LEDs
+    ///
+    /// Instead of rounding each LED on its own, the part of each
+    /// channel that was rounded away is carried over to the next LED
+    /// of the frame (error diffusion). Averaged over a few LEDs, e.g.
+    /// behind a diffuser, this shows levels in between the 256 steps
+    /// of an LED, which avoids visible banding in very dim gradients.
+    ///
+    /// Frames should be given in the physical order of the LEDs.
+    ///
+    /// Only the rounding of this matrix is dithered, so e.g. the
+    /// identity matrix leaves the frame unchanged. To dither per-LED
+    /// brightness, see [`TrimTable::apply_frame_dithered()`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use choreographer::{correction::ColorMatrix, RGB8};
+    ///
+    /// // Half brightness turns a level of 1 into 0.5, which is rounded up on every LED...
+    /// let matrix = ColorMatrix::from_gains(128, 128, 128);
+    /// let mut frame = [RGB8 { r: 1, g: 1, b: 1 }; 4];
+    /// matrix.apply_frame(&mut frame);
+    /// assert_eq!(frame.iter().map(|c| c.r).sum::<u8>(), 4);
+    ///
+    /// // ...but is spread out to an average of 0.5 when dithering
+    /// let mut frame = [RGB8 { r: 1, g: 1, b: 1 }; 4];
+    /// matrix.apply_frame_dithered(&mut frame);
+    /// assert_eq!(frame.iter().map(|c| c.r).sum::<u8>(), 2);
+    /// ```
+    pub fn apply_frame_dithered(&self, frame: &mut [RGB8]) {
+        let mut carry = [0i32; 3];
+
+        for color in frame.iter_mut() {
+            let mut out = self.mix(*color);
+
+            for (o, err) in out.iter_mut().zip(carry.iter_mut()) {
+                let exact = *o + *err;
+                *o = (exact + ((Self::ONE as i32) / 2)) >> 8;
+
+                // Only the rounding error is carried, not anything lost
+                // to clamping, so a saturated LED doesn't brighten its
+                // neighbors
+                *err = exact - (*o << 8);
+            }
+
+            *color = self.clamp.clamp(out);
+        }
+    }
+}
+
+/// How to handle color channels that exceed the range of a `u8`
+///
+/// When a gain above 1.0 is applied, one channel of a color may
+/// clip before the others. Saturating each channel individually
+/// changes the ratio between channels, which shifts the hue: for
+/// example, a bright orange becomes yellow as red clips and green
+/// continues to increase.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClampMode {
+    /// Saturate each channel individually
+    #[default]
+    Saturate,
+
+    /// If any channel exceeds the maximum, scale all three channels
+    /// down proportionally, so that the ratio between them (and so
+    /// the hue) is preserved
+    PreserveHue,
+}
+
+impl ClampMode {
+    /// Clamp `[r, g, b]` channel values into a color
+    ///
+    /// Negative values are always clamped to zero.
+    pub fn clamp(self, channels: [i32; 3]) -> RGB8 {
+        let [r, g, b] = channels.map(|c| c.max(0));
+        let max = r.max(g).max(b);
+
+        match self {
+            // Scale in i64, as `c * 255` overflows an i32 for large inputs
+            ClampMode::PreserveHue if max > 255 => RGB8 {
+                r: ((r as i64 * 255) / max as i64) as u8,
+                g: ((g as i64 * 255) / max as i64) as u8,
+                b: ((b as i64 * 255) / max as i64) as u8,
+            },
+            _ => RGB8 {
+                r: r.min(255) as u8,
+                g: g.min(255) as u8,
+                b: b.min(255) as u8,
+            },
+        }
+    }
+}
+
+impl Default for ColorMatrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// A per-LED brightness trim table for up to `N` LEDs
+///
+/// Each LED has a trim level from 0 to 255, which is applied as a
+/// multiplier of `level / 255` to the output color of that LED. By
+/// default, all LEDs are set to 255, which leaves colors unchanged.
+///
+/// This is separate from any global brightness, and is intended to
+/// compensate for differences between individual LEDs, such as LEDs
+/// behind thicker sections of a diffuser, or at the end of a long
+/// power run.
+///
+/// # Example
+///
+/// ```rust
+/// use choreographer::{correction::TrimTable, RGB8};
+///
+/// let mut trim: TrimTable<4> = TrimTable::new();
+/// trim.set(3, 128);
+///
+/// let mut frame = [RGB8 { r: 200, g: 100, b: 50 }; 4];
+/// trim.apply_frame(&mut frame);
+/// assert_eq!(frame[0], RGB8 { r: 200, g: 100, b: 50 });
+/// assert_eq!(frame[3], RGB8 { r: 100, g: 50, b: 25 });
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrimTable<const N: usize> {
+    levels: [u8; N],
+}
+
+impl<const N: usize> TrimTable<N> {
+    /// Create a new trim table, with all LEDs at full brightness
+    pub const fn new() -> Self {
+        Self { levels: [255; N] }
+    }
+
+    /// Create a new trim table from the given levels
+    pub const fn from_levels(levels: [u8; N]) -> Self {
+        Self { levels }
+    }
+
+    /// Set the trim level of a single LED
+    ///
+    /// Indexes outside of the table are ignored
+    pub fn set(&mut self, index: usize, level: u8) {
+        if let Some(l) = self.levels.get_mut(index) {
+            *l = level;
+        }
+    }
+
+    /// Get the trim level of a single LED
+    pub fn get(&self, index: usize) -> Option<u8> {
+        self.levels.get(index).copied()
+    }
+
+    /// Obtain all trim levels
+    pub fn levels(&self) -> &[u8; N] {
+        &self.levels
+    }
+
+    /// Obtain all trim levels mutably
+    pub fn levels_mut(&mut self) -> &mut [u8; N] {
+        &mut self.levels
+    }
+
+    /// Apply the trim table to a frame
+    ///
+    /// The first item of the frame is trimmed by the first
+    /// level of the table, and so on. Any LEDs past the end of
+    /// the table are left unchanged.
+    pub fn apply_frame(&self, frame: &mut [RGB8]) {
+        frame
+            .iter_mut()
+            .zip(self.levels.iter())
+            .for_each(|(c, l)| *c = scale(*c, *l));
+    }
+
+    /// Apply the trim table to a frame, spreading the rounding error
+    /// across neighboring LEDs
+    ///
+    /// This works like [`TrimTable::apply_frame()`], but the part of
+    /// each channel that was rounded away is carried over to the next
+    /// LED, as in [`ColorMatrix::apply_frame_dithered()`]. Trimming
+    /// dim colors otherwise rounds many of them down to zero.
+    pub fn apply_frame_dithered(&self, frame: &mut [RGB8]) {
+        let mut carry = [0i32; 3];
+
+        for (color, level) in frame.iter_mut().zip(self.levels.iter()) {
+            let level = *level as i32;
+            let mut out = [color.r as i32, color.g as i32, color.b as i32];
+
+            // The carried error is at most half a step, so the result
+            // always fits in a u8
+            for (o, err) in out.iter_mut().zip(carry.iter_mut()) {
+                let exact = (*o * level) + *err;
+                *o = (exact + 127).div_euclid(255);
+                *err = exact - (*o * 255);
+            }
+
+            *color = RGB8 {
+                r: out[0] as u8,
+                g: out[1] as u8,
+                b: out[2] as u8,
+            };
+        }
+    }
+}
+
+impl<const N: usize> Default for TrimTable<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClampMode, ColorMatrix, TrimTable};
+    use smart_leds::RGB8;
+
+    #[test]
+    fn identity() {
+        let color = RGB8 {
+            r: 12,
+            g: 34,
+            b: 255,
+        };
+        assert_eq!(ColorMatrix::IDENTITY.apply(color), color);
+    }
+
+    #[test]
+    fn mixing_and_clamping() {
+        // Swap red and green, and double blue
+        let matrix = ColorMatrix::from_f32([[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 2.0]]);
+        let color = RGB8 {
+            r: 10,
+            g: 20,
+            b: 200,
+        };
+        assert_eq!(
+            matrix.apply(color),
+            RGB8 {
+                r: 20,
+                g: 10,
+                b: 255
+            }
+        );
+
+        // Negative contributions clamp at zero
+        let matrix = ColorMatrix::from_f32([[1.0, -1.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+        assert_eq!(matrix.apply(color).r, 0);
+    }
+
+    #[test]
+    fn preserve_hue() {
+        let matrix = ColorMatrix::from_gains(512, 512, 512);
+        let orange = RGB8 {
+            r: 250,
+            g: 100,
+            b: 0,
+        };
+
+        // Saturating shifts the orange towards yellow...
+        assert_eq!(
+            matrix.apply(orange),
+            RGB8 {
+                r: 255,
+                g: 200,
+                b: 0
+            }
+        );
+
+        // ...while preserving the hue keeps the channel ratio
+        let matrix = matrix.with_clamp(ClampMode::PreserveHue);
+        assert_eq!(
+            matrix.apply(orange),
+            RGB8 {
+                r: 255,
+                g: 102,
+                b: 0
+            }
+        );
+    }
+
+    #[test]
+    fn clamp_large_values() {
+        assert_eq!(
+            ClampMode::PreserveHue.clamp([i32::MAX, i32::MAX / 2, -1]),
+            RGB8 {
+                r: 255,
+                g: 127,
+                b: 0
+            }
+        );
+        assert_eq!(
+            ClampMode::Saturate.clamp([i32::MAX, i32::MIN, 0]),
+            RGB8 { r: 255, g: 0, b: 0 }
+        );
+    }
+
+    #[test]
+    fn dithering() {
+        let dim = RGB8 { r: 3, g: 0, b: 1 };
+
+        // A level of 40 turns 3 into ~0.47, which rounds to zero on every LED...
+        let trim: TrimTable<100> = TrimTable::from_levels([40; 100]);
+        let mut frame = [dim; 100];
+        trim.apply_frame(&mut frame);
+        assert!(frame.iter().all(|c| c.r == 0));
+
+        // ...but averages out to the right level when dithered
+        let mut frame = [dim; 100];
+        trim.apply_frame_dithered(&mut frame);
+        let total: u32 = frame.iter().map(|c| c.r as u32).sum();
+        assert_eq!(total, 47);
+        assert!(frame.iter().all(|c| c.r <= 1 && c.g == 0));
+
+        // The same goes for the color matrix
+        let matrix = ColorMatrix::from_gains(40, 40, 40);
+        let mut frame = [dim; 100];
+        matrix.apply_frame_dithered(&mut frame);
+        let total: u32 = frame.iter().map(|c| c.r as u32).sum();
+        assert_eq!(total, 47);
+
+        // Full levels are left unchanged
+        let mut frame = [dim; 100];
+        TrimTable::<100>::new().apply_frame_dithered(&mut frame);
+        assert!(frame.iter().all(|c| *c == dim));
+    }
+}
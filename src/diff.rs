@@ -0,0 +1,246 @@
+//!
+//! Structural comparison of scripts.
+//!
+//! Two [`Action`]s are equal when they were authored the same way,
+//! regardless of whether (or how far) either of them has been played.
+//! This makes it possible to check that a script received from
+//! somewhere else matches the one that was sent, or to find out which
+//! actions of a script were changed by an editor.
+//!
+//! [`Action`]: crate::engine::Action
+
+use crate::engine::Action;
+
+/// The settings of an [`Action`] that differ from another action
+///
+/// Each field is `true` if that setting differs.
+///
+/// [`Action`]: crate::engine::Action
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Fields {
+    /// The behavior, e.g. solid, sin, or fade up
+    pub kind: bool,
+
+    /// The color
+    pub color: bool,
+
+    /// The duration
+    pub duration: bool,
+
+    /// The period
+    pub period: bool,
+
+    /// The phase offset, or whether it is automatically incremented
+    pub phase_offset: bool,
+
+    /// Whether and how often the action repeats
+    pub repeat: bool,
+
+    /// The application-defined tag
+    pub tag: bool,
+}
+
+impl Fields {
+    /// Does any setting differ?
+    pub fn any(&self) -> bool {
+        self.kind
+            || self.color
+            || self.duration
+            || self.period
+            || self.phase_offset
+            || self.repeat
+            || self.tag
+    }
+}
+
+/// A single difference between two scripts, as reported by [`diff()`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difference {
+    /// The action exists in both scripts, but some of its settings differ
+    Changed {
+        /// The index of the action
+        action: usize,
+
+        /// The settings that differ
+        fields: Fields,
+    },
+
+    /// The action only exists in the old script
+    Removed {
+        /// The index of the action
+        action: usize,
+    },
+
+    /// The action only exists in the new script
+    Added {
+        /// The index of the action
+        action: usize,
+    },
+}
+
+/// Compare two scripts action by action, reporting every difference
+///
+/// Actions are compared by position, so inserting an action at the
+/// start of a script reports every following action as changed.
+///
+/// # Example
+///
+/// ```rust
+/// use choreographer::{
+///     diff::{diff, Difference, Fields},
+///     script,
+/// };
+/// # use groundhog::std_timer::Timer;
+/// # type MicroTimer = Timer<1_000_000>;
+///
+/// let old: [_; 2] = script! {
+///     | action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+///     |  solid |    RED |        1000 |         0.0 |               0 |   once |
+///     |    sin |   BLUE |        1000 |      1000.0 |               0 |   once |
+/// };
+/// let new: [_; 1] = script! {
+///     | action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+///     |  solid |  GREEN |        1000 |         0.0 |               0 |   once |
+/// };
+///
+/// let mut changes = diff::<MicroTimer>(&old, &new);
+/// assert_eq!(
+///     changes.next(),
+///     Some(Difference::Changed {
+///         action: 0,
+///         fields: Fields { color: true, ..Fields::default() },
+///     })
+/// );
+/// assert_eq!(changes.next(), Some(Difference::Removed { action: 1 }));
+/// assert_eq!(changes.next(), None);
+/// ```
+pub fn diff<'a, R>(
+    old: &'a [Action<R>],
+    new: &'a [Action<R>],
+) -> impl Iterator<Item = Difference> + 'a {
+    let len = old.len().max(new.len());
+
+    (0..len).filter_map(move |i| match (old.get(i), new.get(i)) {
+        (Some(a), Some(b)) => {
+            let fields = a.diff(b);
+            if fields.any() {
+                Some(Difference::Changed { action: i, fields })
+            } else {
+                None
+            }
+        }
+        (Some(_), None) => Some(Difference::Removed { action: i }),
+        (None, Some(_)) => Some(Difference::Added { action: i }),
+        (None, None) => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, Difference, Fields};
+    use crate::colors::{BLUE, RED};
+    use crate::engine::{Action, LoopBehavior, PhaseIncr, Sequence};
+    use crate::script;
+    use groundhog::std_timer::Timer;
+
+    type MicroTimer = Timer<1_000_000>;
+
+    #[test]
+    fn ignores_runtime_state() {
+        let actions: [Action<MicroTimer>; 2] = [
+            Action::build()
+                .solid()
+                .color(RED)
+                .for_ms(1)
+                .times(3)
+                .finish(),
+            Action::build().seek().color(BLUE).for_ms(1).once().finish(),
+        ];
+
+        // Play the whole script, which updates start times, last
+        // colors, and repeat counts
+        let mut seq: Sequence<MicroTimer, 2> = Sequence::empty();
+        seq.set(&actions, LoopBehavior::OneShot);
+        while seq.poll().is_some() {}
+
+        assert_eq!(diff(&actions, seq.actions()).count(), 0);
+        assert_eq!(&actions[..], seq.actions());
+    }
+
+    #[test]
+    fn ignores_played_phase() {
+        let actions: [Action<MicroTimer>; 3] = [
+            Action::build()
+                .sin()
+                .color(RED)
+                .dur_per_ms(5, 20.0)
+                .phase_offset_ms(PhaseIncr::Millis(3))
+                .once()
+                .finish(),
+            Action::build()
+                .sin()
+                .color(RED)
+                .dur_per_ms(5, 20.0)
+                .phase_offset_ms(PhaseIncr::AutoIncr)
+                .times(2)
+                .finish(),
+            Action::build()
+                .cos()
+                .color(BLUE)
+                .dur_per_ms(5, 20.0)
+                .phase_offset_ms(PhaseIncr::AutoIncrOnStart)
+                .once()
+                .finish(),
+        ];
+
+        // Play through twice, so every automatic phase is updated
+        let mut seq: Sequence<MicroTimer, 3> = Sequence::empty();
+        seq.set(
+            &actions,
+            LoopBehavior::LoopN {
+                current: 0,
+                cycles: 1,
+            },
+        );
+        while seq.poll().is_some() {}
+
+        assert_ne!(seq.actions()[1].phase_offset_ms, 0);
+        assert_eq!(&actions[..], seq.actions());
+
+        // A NaN period is still equal to itself
+        let nan: Action<MicroTimer> = Action::build().sin().period_ms(f32::NAN).finish();
+        assert_eq!(nan, nan.clone());
+    }
+
+    #[test]
+    fn reports_fields() {
+        let a: [Action<MicroTimer>; 1] = script! {
+            | action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+            |    sin |    RED |        1000 |      1000.0 |               0 |   once |
+        };
+        let b: [Action<MicroTimer>; 1] = [Action::build()
+            .cos()
+            .color(RED)
+            .for_ms(1000)
+            .period_ms(500.0)
+            .phase_offset_ms(PhaseIncr::AutoIncr)
+            .forever()
+            .finish()];
+
+        assert_eq!(
+            a[0].diff(&b[0]),
+            Fields {
+                kind: true,
+                period: true,
+                phase_offset: true,
+                repeat: true,
+                ..Fields::default()
+            }
+        );
+        assert_ne!(a, b);
+        assert_eq!(
+            diff(&b, &[]).collect::<std::vec::Vec<_>>(),
+            [Difference::Removed { action: 0 }]
+        );
+    }
+}
@@ -52,7 +52,7 @@
 //!
 //! This project is licensed under the [Mozilla Public License v2.0](https://www.mozilla.org/en-US/MPL/2.0/).
 
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![deny(missing_docs)]
 
 /// Individual color behavior steps
@@ -61,6 +61,39 @@ pub mod behaviors;
 /// The choreographer sequencing engine
 pub mod engine;
 
+/// Output color correction and calibration
+pub mod correction;
+
+/// Saturating math helpers for colors
+pub mod color_math;
+
+/// Named groups of LEDs
+pub mod zones;
+
+/// Multi-LED choreographies
+pub mod choreography;
+
+/// Export of scripts to WLED presets
+pub mod wled;
+
+/// Validation of scripts
+pub mod validate;
+
+/// Structural comparison of scripts
+pub mod diff;
+
+/// Tolerance-based assertions for tests
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+/// An Open Pixel Control client
+#[cfg(feature = "std")]
+pub mod opc;
+
+/// Offline power estimates
+#[cfg(feature = "std")]
+pub mod power;
+
 /// The color types from the [`smart-leds`](https://docs.rs/smart-leds) crate
 pub use smart_leds::colors;
 
@@ -153,6 +186,60 @@ macro_rules! script {
     };
 }
 
+/// The `choreography!()` macro for defining the [`Row`]s of a choreography
+///
+/// Each row names a [`Target`] constructor (`led`, `range`, or `zone`),
+/// the start time of the row in milliseconds, and the name of a script
+/// of [`Action`]s, such as one created with [`script!()`].
+///
+/// ```rust
+/// use choreographer::{
+///     choreography,
+///     choreography::compile,
+///     engine::{LoopBehavior, Sequence},
+///     script,
+///     zones::{Zone, Zones},
+/// };
+/// # use groundhog::std_timer::Timer;
+/// # type MicroTimer = Timer<1_000_000>;
+///
+/// const ZONES: Zones = Zones::new(&[Zone::range("ring", 0, 12)]);
+///
+/// let pulse = script! {
+///     | action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+///     |    sin |  WHITE |        1000 |      1000.0 |               0 |   once |
+/// };
+///
+/// let rows = choreography! {
+///     |        target | start_ms | actions |
+///     |  zone("ring") |        0 |   pulse |
+///     | range(12, 16) |      500 |   pulse |
+///     |       led(12) |     1500 |   pulse |
+/// };
+///
+/// let mut leds: [Sequence<MicroTimer, 8>; 16] = Sequence::new_array();
+/// compile(&rows, &ZONES, &mut leds, LoopBehavior::LoopForever).unwrap();
+/// ```
+///
+/// [`Row`]: crate::choreography::Row
+/// [`Target`]: crate::choreography::Target
+/// [`Action`]: crate::engine::Action
+/// [`script!()`]: crate::script
+#[macro_export]
+macro_rules! choreography {
+    (| target | start_ms | actions | $(| $target:ident ( $($arg:expr),* ) | $start_ms:literal | $actions:ident |)+) => {
+        [
+            $(
+                $crate::choreography::Row::new(
+                    $crate::choreography::Target::$target($($arg),*),
+                    $start_ms,
+                    &$actions,
+                ),
+            )+
+        ]
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
@@ -0,0 +1,133 @@
+//!
+//! A client for the [Open Pixel Control] protocol.
+//!
+//! This allows frames of colors to be sent to an OPC server, such as
+//! a [Fadecandy] server or an LED simulator, which is often the easiest
+//! way to preview a choreography without the real hardware.
+//!
+//! A frame is any slice of colors, with one color per LED, e.g. the
+//! results of polling an array of [`Sequence`]s.
+//!
+//! This module requires the `std` feature.
+//!
+//! [Open Pixel Control]: http://openpixelcontrol.org/
+//! [Fadecandy]: https://github.com/scanlime/fadecandy
+//! [`Sequence`]: crate::engine::Sequence
+
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+use std::vec::Vec;
+
+use smart_leds::RGB8;
+
+/// The OPC "Set Pixel Colors" command
+const CMD_SET_PIXELS: u8 = 0;
+
+/// The length of an OPC message header
+const HEADER_LEN: usize = 4;
+
+/// A client that sends frames to an OPC server
+///
+/// Frames are sent at most at the configured rate. Frames submitted
+/// more often than this are dropped, which allows [`OpcClient::send()`]
+/// to be called on every update of the LEDs.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use choreographer::{opc::OpcClient, RGB8};
+///
+/// let mut client = OpcClient::connect("127.0.0.1:7890", 0, 60).unwrap();
+///
+/// let frame = [RGB8 { r: 255, g: 0, b: 0 }; 64];
+/// client.send(&frame).unwrap();
+/// ```
+pub struct OpcClient {
+    stream: TcpStream,
+    channel: u8,
+    interval: Duration,
+    last_sent: Option<Instant>,
+    buf: Vec<u8>,
+}
+
+impl OpcClient {
+    /// Connect to an OPC server
+    ///
+    /// Frames will be sent on the given OPC `channel` (0 is usually
+    /// used to mean "all channels"), at most `max_fps` times per second.
+    pub fn connect<A: ToSocketAddrs>(addr: A, channel: u8, max_fps: u32) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+
+        Ok(Self {
+            stream,
+            channel,
+            interval: Duration::from_secs(1) / max_fps.max(1),
+            last_sent: None,
+            buf: Vec::new(),
+        })
+    }
+
+    /// Send a frame to the server
+    ///
+    /// Returns `Ok(false)` if the frame was dropped because the previous
+    /// frame was sent too recently, or `Ok(true)` if it was sent.
+    ///
+    /// Frames of more than 21845 LEDs can not be represented by OPC,
+    /// and will be rejected with an error of kind [`io::ErrorKind::InvalidInput`].
+    pub fn send(&mut self, frame: &[RGB8]) -> io::Result<bool> {
+        let now = Instant::now();
+        if let Some(last) = self.last_sent {
+            if now.duration_since(last) < self.interval {
+                return Ok(false);
+            }
+        }
+
+        let data_len = frame.len() * 3;
+        if data_len > (u16::MAX as usize) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame too large for OPC",
+            ));
+        }
+
+        self.buf.clear();
+        self.buf.reserve(HEADER_LEN + data_len);
+        self.buf.push(self.channel);
+        self.buf.push(CMD_SET_PIXELS);
+        self.buf.extend_from_slice(&(data_len as u16).to_be_bytes());
+        for c in frame {
+            self.buf.extend_from_slice(&[c.r, c.g, c.b]);
+        }
+
+        self.stream.write_all(&self.buf)?;
+        self.last_sent = Some(now);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OpcClient;
+    use smart_leds::RGB8;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn sends_frames() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = OpcClient::connect(listener.local_addr().unwrap(), 1, 1).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+
+        let frame = [RGB8 { r: 1, g: 2, b: 3 }, RGB8 { r: 4, g: 5, b: 6 }];
+        assert!(client.send(&frame).unwrap());
+
+        // Too soon for the next frame at 1fps
+        assert!(!client.send(&frame).unwrap());
+
+        let mut msg = [0u8; 10];
+        server.read_exact(&mut msg).unwrap();
+        assert_eq!(msg, [1, 0, 0, 6, 1, 2, 3, 4, 5, 6]);
+    }
+}
@@ -0,0 +1,288 @@
+//!
+//! Offline estimates of the power used by a choreography.
+//!
+//! This allows batteries and power supplies to be sized before the
+//! hardware exists. Sequences are played on a [`SimTimer`], which
+//! only moves forward when told to, so an entire show can be
+//! simulated in a fraction of its real play time, with exactly the
+//! same colors every time.
+//!
+//! The colors of every step are converted to a current using an
+//! [`LedModel`], and summed up into a [`PowerReport`].
+//!
+//! This module requires the `std` feature.
+//!
+//! [`SimTimer`]: crate::power::SimTimer
+//! [`LedModel`]: crate::power::LedModel
+//! [`PowerReport`]: crate::power::PowerReport
+
+use std::cell::Cell;
+use std::time::Duration;
+
+use crate::engine::Sequence;
+use groundhog::RollingTimer;
+use smart_leds::RGB8;
+
+std::thread_local! {
+    /// The simulated time of the current thread, in nanoseconds
+    static SIM_NANOS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// A rolling timer that reads a simulated clock
+///
+/// The clock starts at zero, and only moves forward when [`advance()`]
+/// is called. Each thread has its own clock, which is shared by all
+/// `SimTimer`s on that thread, regardless of their tick rate.
+///
+/// [`advance()`]: crate::power::advance
+#[derive(Clone, Debug, Default)]
+pub struct SimTimer<const TPS: u32>;
+
+impl<const TPS: u32> RollingTimer for SimTimer<TPS> {
+    type Tick = u32;
+    const TICKS_PER_SECOND: u32 = TPS;
+
+    fn get_ticks(&self) -> u32 {
+        let nanos = SIM_NANOS.with(|n| n.get()) as u128;
+        // Truncation is intended, the timer rolls over
+        (nanos * (TPS as u128) / 1_000_000_000) as u32
+    }
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn ticks_since(&self, rhs: u32) -> u32 {
+        self.get_ticks().wrapping_sub(rhs)
+    }
+
+    fn seconds_since(&self, rhs: u32) -> u32 {
+        self.ticks_since(rhs) / TPS.max(1)
+    }
+
+    fn millis_since(&self, rhs: u32) -> u32 {
+        ((self.ticks_since(rhs) as u64) * 1_000 / (TPS.max(1) as u64)) as u32
+    }
+
+    fn micros_since(&self, rhs: u32) -> u32 {
+        ((self.ticks_since(rhs) as u64) * 1_000_000 / (TPS.max(1) as u64)) as u32
+    }
+}
+
+/// Move the simulated clock of the current thread forward
+pub fn advance(by: Duration) {
+    let by = by.as_nanos().min(u64::MAX as u128) as u64;
+    SIM_NANOS.with(|n| n.set(n.get().wrapping_add(by)));
+}
+
+/// An electrical model of a single LED
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LedModel {
+    /// The current drawn by the red, green and blue channels at
+    /// full brightness, in milliamps
+    pub channel_ma: [f32; 3],
+
+    /// The current drawn by the LED when it is off, in milliamps
+    pub idle_ma: f32,
+
+    /// The supply voltage, in volts
+    pub volts: f32,
+}
+
+impl LedModel {
+    /// A typical WS2812B at 5V
+    pub const WS2812B: Self = Self {
+        channel_ma: [20.0, 20.0, 20.0],
+        idle_ma: 1.0,
+        volts: 5.0,
+    };
+
+    /// The current drawn by a single LED showing `color`, in milliamps
+    ///
+    /// The current of each channel is assumed to scale linearly with
+    /// its value.
+    pub fn current_ma(&self, color: RGB8) -> f32 {
+        let [r, g, b] = self.channel_ma;
+        self.idle_ma
+            + r * (color.r as f32 / 255.0)
+            + g * (color.g as f32 / 255.0)
+            + b * (color.b as f32 / 255.0)
+    }
+
+    /// The current drawn by a whole frame of LEDs, in milliamps
+    pub fn frame_ma(&self, frame: &[RGB8]) -> f32 {
+        frame.iter().map(|c| self.current_ma(*c)).sum()
+    }
+}
+
+/// The results of a [`simulate()`] run
+///
+/// [`simulate()`]: crate::power::simulate
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PowerReport {
+    /// The simulated play time, in milliseconds
+    pub duration_ms: u64,
+
+    /// Did every sequence finish before the time limit?
+    pub finished: bool,
+
+    /// The average current over the whole run, in milliamps
+    pub average_ma: f32,
+
+    /// The highest current of any single step, in milliamps
+    pub peak_ma: f32,
+
+    /// The charge used over the whole run, in milliamp hours
+    pub charge_mah: f32,
+
+    /// The energy used over the whole run, in milliwatt hours
+    pub energy_mwh: f32,
+}
+
+/// Play the sequences on a simulated clock, and report the power used
+///
+/// All sequences are polled every `step_ms`, until they have all
+/// finished, or `max_ms` has passed, whichever comes first. Sequences
+/// that have finished are counted as off. The current of each step is
+/// taken to be constant until the next step.
+///
+/// The simulated clock of the current thread is moved forward by the
+/// play time of the run.
+///
+/// # Example
+///
+/// ```rust
+/// use choreographer::{
+///     engine::{LoopBehavior, Sequence},
+///     power::{simulate, LedModel, SimTimer},
+///     script,
+/// };
+///
+/// let mut leds: [Sequence<SimTimer<1_000_000>, 4>; 8] = Sequence::new_array();
+/// for led in leds.iter_mut() {
+///     led.set(&script!(
+///         | action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+///         |  solid |  WHITE |        1000 |         0.0 |               0 |   once |
+///         |  solid |  BLACK |        1000 |         0.0 |               0 |   once |
+///     ), LoopBehavior::OneShot);
+/// }
+///
+/// let report = simulate(&mut leds, &LedModel::WS2812B, 10, 60_000);
+/// assert!(report.finished);
+/// assert_eq!(report.duration_ms, 2000);
+/// assert_eq!(report.peak_ma, 8.0 * 61.0);
+/// assert_eq!(report.average_ma, 8.0 * 31.0);
+/// ```
+pub fn simulate<const TPS: u32, const N: usize>(
+    leds: &mut [Sequence<SimTimer<TPS>, N>],
+    model: &LedModel,
+    step_ms: u32,
+    max_ms: u64,
+) -> PowerReport {
+    let step_ms = step_ms.max(1);
+    let step = Duration::from_millis(step_ms as u64);
+
+    let mut elapsed_ms = 0u64;
+    let mut finished = false;
+    let mut peak_ma = 0.0f32;
+
+    // Sum of milliamps times milliseconds
+    let mut total = 0.0f64;
+
+    while elapsed_ms < max_ms {
+        let mut playing = false;
+        let mut current = 0.0f32;
+        for led in leds.iter_mut() {
+            let color = match led.poll() {
+                Some(color) => {
+                    playing = true;
+                    color
+                }
+                None => RGB8::default(),
+            };
+            current += model.current_ma(color);
+        }
+
+        if !playing {
+            finished = true;
+            break;
+        }
+
+        peak_ma = peak_ma.max(current);
+        total += current as f64 * step_ms as f64;
+        elapsed_ms += step_ms as u64;
+        advance(step);
+    }
+
+    let average_ma = if elapsed_ms == 0 {
+        0.0
+    } else {
+        total / elapsed_ms as f64
+    };
+    let charge_mah = total / 3_600_000.0;
+
+    PowerReport {
+        duration_ms: elapsed_ms,
+        finished,
+        average_ma: average_ma as f32,
+        peak_ma,
+        charge_mah: charge_mah as f32,
+        energy_mwh: (charge_mah * model.volts as f64) as f32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{advance, simulate, LedModel, SimTimer};
+    use crate::colors::{BLACK, RED};
+    use crate::engine::{Action, LoopBehavior, Sequence};
+    use groundhog::RollingTimer;
+    use smart_leds::RGB8;
+    use std::time::Duration;
+
+    #[test]
+    fn sim_timer() {
+        let timer = SimTimer::<1_000_000>;
+        let start = timer.get_ticks();
+
+        // Only moves when advanced
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(timer.ticks_since(start), 0);
+
+        advance(Duration::from_micros(2500));
+        assert_eq!(timer.ticks_since(start), 2500);
+        assert_eq!(timer.millis_since(start), 2);
+        assert_eq!(timer.micros_since(start), 2500);
+    }
+
+    #[test]
+    fn model() {
+        let model = LedModel::WS2812B;
+        assert_eq!(model.current_ma(BLACK), 1.0);
+        assert_eq!(model.current_ma(RED), 21.0);
+        assert_eq!(model.current_ma(RGB8::new(255, 255, 255)), 61.0);
+        assert_eq!(model.frame_ma(&[BLACK, RED]), 22.0);
+    }
+
+    #[test]
+    fn forever() {
+        let mut leds: [Sequence<SimTimer<1_000_000>, 1>; 2] = Sequence::new_array();
+        leds[0].set(
+            &[Action::build()
+                .solid()
+                .color(RED)
+                .for_ms(100)
+                .once()
+                .finish()],
+            LoopBehavior::LoopForever,
+        );
+
+        // One red LED, and one that never plays
+        let report = simulate(&mut leds, &LedModel::WS2812B, 5, 3_600_000);
+        assert!(!report.finished);
+        assert_eq!(report.duration_ms, 3_600_000);
+        assert_eq!(report.peak_ma, 22.0);
+        assert!((report.charge_mah - 22.0).abs() < 0.01, "{:?}", report);
+        assert!((report.energy_mwh - 110.0).abs() < 0.05, "{:?}", report);
+    }
+}
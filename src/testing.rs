@@ -0,0 +1,144 @@
+//!
+//! Helpers for testing code that uses choreographer.
+//!
+//! Most behaviors are calculated with floating point math, which may
+//! give slightly different results depending on the math backend and
+//! target. These helpers compare colors within some tolerance, and
+//! check the general shape of a series of colors, rather than
+//! requiring exact matches.
+//!
+//! This module requires the `testing` feature.
+
+use smart_leds::RGB8;
+
+/// Are the two colors within `tolerance` of each other, on every channel?
+pub fn color_near(a: RGB8, b: RGB8, tolerance: u8) -> bool {
+    (a.r as i16 - b.r as i16).abs() <= tolerance as i16
+        && (a.g as i16 - b.g as i16).abs() <= tolerance as i16
+        && (a.b as i16 - b.b as i16).abs() <= tolerance as i16
+}
+
+/// The brightness of a color, e.g. the value of its brightest channel
+pub fn brightness(color: RGB8) -> u8 {
+    color.r.max(color.g).max(color.b)
+}
+
+/// Assert that two colors are within `tolerance` of each other, on
+/// every channel
+///
+/// # Example
+///
+/// ```rust
+/// use choreographer::{testing::assert_color_near, RGB8};
+///
+/// assert_color_near(RGB8::new(10, 20, 30), RGB8::new(11, 19, 30), 1);
+/// ```
+#[track_caller]
+pub fn assert_color_near(actual: RGB8, expected: RGB8, tolerance: u8) {
+    if !color_near(actual, expected, tolerance) {
+        panic!(
+            "color {:?} is not within {} of {:?}",
+            actual, tolerance, expected
+        );
+    }
+}
+
+/// Assert that every sample is within `tolerance` of the color given
+/// by `expected` for the index of that sample
+///
+/// This is useful to check a series of polled colors against the
+/// ideal curve of a behavior.
+#[track_caller]
+pub fn assert_envelope<F>(samples: &[RGB8], mut expected: F, tolerance: u8)
+where
+    F: FnMut(usize) -> RGB8,
+{
+    for (i, sample) in samples.iter().enumerate() {
+        let exp = expected(i);
+        if !color_near(*sample, exp, tolerance) {
+            panic!(
+                "sample {}: color {:?} is not within {} of {:?}",
+                i, sample, tolerance, exp
+            );
+        }
+    }
+}
+
+/// Assert that the brightness of the samples never decreases by more
+/// than `tolerance` from one sample to the next
+#[track_caller]
+pub fn assert_rising(samples: &[RGB8], tolerance: u8) {
+    for (i, pair) in samples.windows(2).enumerate() {
+        let (a, b) = (brightness(pair[0]), brightness(pair[1]));
+        if b.saturating_add(tolerance) < a {
+            panic!("brightness falls from {} to {} at sample {}", a, b, i + 1);
+        }
+    }
+}
+
+/// Assert that the brightness of the samples never increases by more
+/// than `tolerance` from one sample to the next
+#[track_caller]
+pub fn assert_falling(samples: &[RGB8], tolerance: u8) {
+    for (i, pair) in samples.windows(2).enumerate() {
+        let (a, b) = (brightness(pair[0]), brightness(pair[1]));
+        if a.saturating_add(tolerance) < b {
+            panic!("brightness rises from {} to {} at sample {}", a, b, i + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assert_color_near, assert_falling, assert_rising, brightness};
+    use crate::colors::{BLACK, WHITE};
+    use crate::engine::{Action, LoopBehavior, Sequence};
+    use groundhog::std_timer::Timer;
+    use smart_leds::RGB8;
+    use std::thread::sleep;
+    use std::time::Duration;
+    use std::vec::Vec;
+
+    type MicroTimer = Timer<1_000_000>;
+
+    #[test]
+    fn fades() {
+        let mut seq: Sequence<MicroTimer, 2> = Sequence::empty();
+        seq.set(
+            &[
+                Action::build()
+                    .fade_up()
+                    .color(WHITE)
+                    .for_ms(50)
+                    .once()
+                    .finish(),
+                Action::build()
+                    .fade_down()
+                    .color(WHITE)
+                    .for_ms(50)
+                    .once()
+                    .finish(),
+            ],
+            LoopBehavior::OneShot,
+        );
+
+        let mut samples = Vec::new();
+        while let Some(color) = seq.poll() {
+            samples.push(color);
+            sleep(Duration::from_millis(2));
+        }
+
+        let peak = (0..samples.len())
+            .max_by_key(|i| brightness(samples[*i]))
+            .unwrap();
+        assert_rising(&samples[..=peak], 1);
+        assert_falling(&samples[peak..], 1);
+        assert_color_near(samples[0], BLACK, 32);
+    }
+
+    #[test]
+    #[should_panic]
+    fn not_near() {
+        assert_color_near(RGB8::new(10, 20, 30), RGB8::new(10, 22, 30), 1);
+    }
+}
@@ -0,0 +1,247 @@
+//!
+//! Checks for common mistakes in scripts, such as actions that
+//! will never be visible, or cyclers with a period that can not
+//! be played.
+//!
+//! These checks are intended to be shared by anything that creates
+//! scripts, such as authoring tools, or code that receives scripts
+//! at runtime, so that all of them agree on what a "valid" script is.
+
+use crate::engine::{
+    max_duration_ms, total_play_time, Action, InnerActionKind, LoopBehavior, PlayTime,
+};
+use groundhog::RollingTimer;
+use heapless::Vec;
+
+/// A problem found in a script
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LintError {
+    /// The action has a duration of zero, and will never be shown
+    ZeroDuration {
+        /// The index of the action
+        action: usize,
+    },
+
+    /// The duration of the action is too long to be measured by the
+    /// timer, and will wrap around
+    DurationTooLong {
+        /// The index of the action
+        action: usize,
+    },
+
+    /// The period of a cycler or fade is zero, negative, NaN, or infinite
+    InvalidPeriod {
+        /// The index of the action
+        action: usize,
+    },
+
+    /// The phase offset of a cycler is longer than its period
+    PhaseExceedsPeriod {
+        /// The index of the action
+        action: usize,
+    },
+
+    /// The action comes after an action that loops forever, and
+    /// will never be reached
+    Unreachable {
+        /// The index of the action
+        action: usize,
+    },
+
+    /// The script will never finish playing, because the sequence
+    /// or one of its actions loops forever
+    NeverTerminates,
+}
+
+/// Check a script for problems
+///
+/// All problems are reported, up to a maximum of `M`. Any problems
+/// beyond that are not included, so the list may be truncated, but
+/// an error is returned whenever there is at least one problem, even
+/// if `M` is zero.
+///
+/// # Example
+///
+/// ```rust
+/// use choreographer::{engine::Action, script, validate::{validate, LintError}};
+/// # use groundhog::std_timer::Timer;
+/// # type MicroTimer = Timer<1_000_000>;
+///
+/// let actions: [Action<MicroTimer>; 2] = script! {
+///     | action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+///     |  solid |    RED |           0 |         0.0 |               0 |   once |
+///     |    sin |   BLUE |        1000 |         0.0 |               0 |   once |
+/// };
+///
+/// let errors = validate::<_, 4>(&actions).unwrap_err();
+/// assert_eq!(
+///     errors,
+///     [
+///         LintError::ZeroDuration { action: 0 },
+///         LintError::InvalidPeriod { action: 1 },
+///     ]
+/// );
+/// ```
+pub fn validate<R, const M: usize>(actions: &[Action<R>]) -> Result<(), Vec<LintError, M>>
+where
+    R: RollingTimer<Tick = u32> + Default + Clone,
+{
+    let mut errors = Vec::new();
+    let mut found = false;
+    lint_actions(actions, |e| {
+        found = true;
+        errors.push(e).ok();
+    });
+
+    if found {
+        Err(errors)
+    } else {
+        Ok(())
+    }
+}
+
+/// Check a script for problems, and also make sure that it will
+/// eventually finish when played with the given sequence `behavior`
+///
+/// This is useful when a script must end, e.g. a notification that
+/// should return to some other script when complete.
+///
+/// As with [`validate()`], the list of problems may be truncated to
+/// `M` entries, but an error is returned whenever there is a problem.
+///
+/// A script terminates when its [`total_play_time()`] is finite, so
+/// e.g. an empty script, or an action that loops forever but has a
+/// duration of zero, do terminate.
+///
+/// [`total_play_time()`]: crate::engine::total_play_time
+pub fn validate_terminating<R, const M: usize>(
+    actions: &[Action<R>],
+    behavior: &LoopBehavior,
+) -> Result<(), Vec<LintError, M>>
+where
+    R: RollingTimer<Tick = u32> + Default + Clone,
+{
+    let mut errors = Vec::new();
+    let mut found = false;
+
+    lint_actions(actions, |e| {
+        found = true;
+        errors.push(e).ok();
+    });
+
+    if total_play_time(actions, behavior) == PlayTime::Infinite {
+        found = true;
+        errors.push(LintError::NeverTerminates).ok();
+    }
+
+    if found {
+        Err(errors)
+    } else {
+        Ok(())
+    }
+}
+
+fn lint_actions<R, F>(actions: &[Action<R>], mut report: F)
+where
+    R: RollingTimer<Tick = u32> + Default + Clone,
+    F: FnMut(LintError),
+{
+    let max_ms = max_duration_ms::<R>();
+
+    let mut after_forever = false;
+
+    for (i, action) in actions.iter().enumerate() {
+        let behavior = action.loop_behavior();
+        if let LoopBehavior::Nop = behavior {
+            continue;
+        }
+
+        if after_forever {
+            report(LintError::Unreachable { action: i });
+        }
+        after_forever |= matches!(behavior, LoopBehavior::LoopForever);
+
+        if action.duration_ms == 0 {
+            report(LintError::ZeroDuration { action: i });
+        } else if action.duration_ms > max_ms {
+            report(LintError::DurationTooLong { action: i });
+        }
+
+        // Fades are cyclers too. Their period is derived from their
+        // duration by `for_ms()`, but may be overwritten afterwards,
+        // e.g. by the `period_ms_f` column of `script!()`
+        let period = action.period_ms;
+        let period_valid = period.is_finite() && period > 0.0;
+        match action.kind() {
+            InnerActionKind::Sin(_) | InnerActionKind::Fade(_) if !period_valid => {
+                report(LintError::InvalidPeriod { action: i });
+            }
+            InnerActionKind::Sin(_) if (action.phase_offset_ms as f32) > period => {
+                report(LintError::PhaseExceedsPeriod { action: i });
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate, validate_terminating, LintError};
+    use crate::engine::{Action, LoopBehavior};
+    use crate::script;
+    use groundhog::std_timer::Timer;
+
+    type MicroTimer = Timer<1_000_000>;
+
+    #[test]
+    fn fade_period() {
+        // The period column overwrites the period derived by `for_ms()`
+        let actions: [Action<MicroTimer>; 2] = script! {
+            |  action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+            | fade_up |  WHITE |        1000 |         0.0 |               0 |   once |
+            | fade_up |  WHITE |        1000 |      2000.0 |               0 |   once |
+        };
+
+        let errors = validate::<_, 4>(&actions).unwrap_err();
+        assert_eq!(errors, [LintError::InvalidPeriod { action: 0 }]);
+    }
+
+    #[test]
+    fn terminating() {
+        let empty: [Action<MicroTimer>; 0] = [];
+        assert_eq!(
+            validate_terminating::<_, 4>(&empty, &LoopBehavior::LoopForever),
+            Ok(())
+        );
+
+        let actions: [Action<MicroTimer>; 2] = script! {
+            | action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+            |  solid |    RED |        1000 |         0.0 |               0 |   once |
+            |  solid |   BLUE |        1000 |         0.0 |               0 | forever |
+        };
+        assert_eq!(
+            validate_terminating::<_, 4>(&actions, &LoopBehavior::OneShot).unwrap_err(),
+            [LintError::NeverTerminates]
+        );
+        assert_eq!(
+            validate_terminating::<_, 4>(&actions, &LoopBehavior::Nop),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let actions: [Action<MicroTimer>; 2] = script! {
+            | action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+            |  solid |    RED |           0 |         0.0 |               0 |   once |
+            |  solid |   BLUE |           0 |         0.0 |               0 | forever |
+        };
+
+        assert_eq!(validate::<_, 0>(&actions).unwrap_err(), []);
+        assert_eq!(
+            validate::<_, 1>(&actions).unwrap_err(),
+            [LintError::ZeroDuration { action: 0 }]
+        );
+        assert!(validate_terminating::<_, 0>(&actions, &LoopBehavior::LoopForever).is_err());
+    }
+}
@@ -0,0 +1,480 @@
+//!
+//! Export scripts for playback on [WLED] controllers.
+//!
+//! WLED does not have a way to describe arbitrary color sequences,
+//! so a script is approximated as a WLED *playlist*: each [`Action`]
+//! becomes one preset showing a built in WLED effect on a segment,
+//! and the playlist steps through these presets with the same timing
+//! as the script.
+//!
+//! The output is written in the format of WLED's `presets.json`,
+//! which can be uploaded to a controller as-is.
+//!
+//! This is a best-effort conversion. Whenever something can not be
+//! represented exactly, a [`Warning`] is reported, and the closest
+//! available WLED behavior is used instead:
+//!
+//! | action               | WLED preset                                 |
+//! | :------------------- | :------------------------------------------ |
+//! | `solid`              | "Solid" effect                              |
+//! | `seek`               | "Solid" effect, with a transition           |
+//! | `fade_up`            | "Solid" effect, with a transition           |
+//! | `fade_down`          | "Solid" black, with a transition            |
+//! | `sin`, `cos`         | "Breathe" effect, with an approximate speed |
+//!
+//! [WLED]: https://kno.wled.ge/
+//! [`Action`]: crate::engine::Action
+//! [`Warning`]: crate::wled::Warning
+
+use core::fmt::{self, Write};
+
+use crate::engine::{Action, InnerActionKind, LoopBehavior};
+use groundhog::RollingTimer;
+use smart_leds::colors::BLACK;
+use smart_leds::RGB8;
+
+/// The highest preset ID supported by WLED
+const MAX_PRESET: usize = 250;
+
+/// The longest duration or transition of a WLED playlist entry,
+/// in tenths of a second
+const MAX_TENTHS: u32 = u16::MAX as u32;
+
+/// The WLED "Solid" effect
+const FX_SOLID: u8 = 0;
+
+/// The WLED "Breathe" effect
+const FX_BREATHE: u8 = 2;
+
+/// Settings for exporting a script to WLED
+#[derive(Clone, Copy, Debug)]
+pub struct Export<'a> {
+    /// The name of the playlist. Presets for each action are named
+    /// after the playlist, followed by the index of the action.
+    pub name: &'a str,
+
+    /// The preset ID of the first action. The following actions use
+    /// the IDs after this, and the playlist itself uses the ID after
+    /// the last action. WLED preset IDs start at 1.
+    pub first_preset: u8,
+
+    /// The first LED of the WLED segment
+    pub start: usize,
+
+    /// One past the last LED of the WLED segment
+    pub stop: usize,
+}
+
+impl<'a> Export<'a> {
+    /// Export to the given range of LEDs, starting at preset ID 1
+    pub fn new(name: &'a str, start: usize, stop: usize) -> Self {
+        Self {
+            name,
+            first_preset: 1,
+            start,
+            stop,
+        }
+    }
+}
+
+/// A difference between the original script and the exported presets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Warning {
+    /// The action has no exact WLED equivalent, and was replaced
+    /// with the closest available effect
+    ApproximateEffect {
+        /// The index of the action
+        action: usize,
+    },
+
+    /// The phase offset of the action is not supported, and was ignored
+    PhaseOffsetIgnored {
+        /// The index of the action
+        action: usize,
+    },
+
+    /// The duration of the action was rounded to the 100ms
+    /// resolution of WLED playlists, or shortened to the longest
+    /// duration WLED supports (6553.5s)
+    DurationRounded {
+        /// The index of the action
+        action: usize,
+    },
+
+    /// The action comes after an action that loops forever, and
+    /// would never be reached
+    Unreachable {
+        /// The index of the action
+        action: usize,
+    },
+}
+
+/// An error that prevented the export
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportError {
+    /// The script needs more presets than WLED supports, starting
+    /// from the configured first preset
+    TooManyPresets,
+
+    /// The configured first preset is 0, which WLED does not allow
+    InvalidFirstPreset,
+
+    /// Writing to the output failed
+    Fmt,
+}
+
+impl From<fmt::Error> for ExportError {
+    fn from(_: fmt::Error) -> Self {
+        ExportError::Fmt
+    }
+}
+
+/// Export a script to WLED presets, written to `out`
+///
+/// `behavior` is the [`LoopBehavior`] the script would be played
+/// with in a [`Sequence`], and sets how often the playlist repeats.
+/// Any [`Warning`]s are passed to `warn` as they are found.
+///
+/// # Example
+///
+/// ```rust
+/// use choreographer::{
+///     engine::{Action, LoopBehavior},
+///     script,
+///     wled::{export, Export},
+/// };
+/// # use groundhog::std_timer::Timer;
+/// # type MicroTimer = Timer<1_000_000>;
+///
+/// let actions: [Action<MicroTimer>; 2] = script! {
+///     | action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+///     |  solid |    RED |        1000 |         0.0 |               0 |   once |
+///     |   seek |   BLUE |         500 |         0.0 |               0 |   once |
+/// };
+///
+/// let mut json = String::new();
+/// export(
+///     &Export::new("demo", 0, 30),
+///     &actions,
+///     &LoopBehavior::LoopForever,
+///     &mut json,
+///     |w| println!("warning: {:?}", w),
+/// ).unwrap();
+/// ```
+///
+/// [`Sequence`]: crate::engine::Sequence
+pub fn export<R, W, F>(
+    config: &Export<'_>,
+    actions: &[Action<R>],
+    behavior: &LoopBehavior,
+    out: &mut W,
+    mut warn: F,
+) -> Result<(), ExportError>
+where
+    R: RollingTimer<Tick = u32> + Default + Clone,
+    W: Write,
+    F: FnMut(Warning),
+{
+    if config.first_preset == 0 {
+        return Err(ExportError::InvalidFirstPreset);
+    }
+
+    // Figure out which actions will actually be played
+    let mut forever = None;
+    let mut count = 0;
+    for (i, action) in actions.iter().enumerate() {
+        match action.loop_behavior() {
+            LoopBehavior::Nop => continue,
+            _ if forever.is_some() => {
+                warn(Warning::Unreachable { action: i });
+                continue;
+            }
+            LoopBehavior::LoopForever => forever = Some(i),
+            _ => {}
+        }
+        count += 1;
+    }
+    let playable = || {
+        actions
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| !matches!(a.loop_behavior(), LoopBehavior::Nop))
+            .take(count)
+    };
+
+    if count == 0 || matches!(behavior, LoopBehavior::Nop) {
+        out.write_str("{}")?;
+        return Ok(());
+    }
+
+    let first = config.first_preset as usize;
+    if first + count > MAX_PRESET {
+        return Err(ExportError::TooManyPresets);
+    }
+    let playlist_id = first + count;
+
+    out.write_char('{')?;
+
+    for (n, (i, action)) in playable().enumerate() {
+        let preset = Preset::from_action(i, action, &mut warn);
+        write!(out, "\"{}\":{{\"n\":\"", first + n)?;
+        write_escaped(out, config.name)?;
+        write!(
+            out,
+            " {}\",\"on\":true,\"seg\":[{{\"id\":0,\"start\":{},\"stop\":{},\"fx\":{},\"sx\":{},\"col\":[[{},{},{}]]}}]}},",
+            i,
+            config.start,
+            config.stop,
+            preset.fx,
+            preset.sx,
+            preset.color.r,
+            preset.color.g,
+            preset.color.b,
+        )?;
+    }
+
+    write!(out, "\"{}\":{{\"n\":\"", playlist_id)?;
+    write_escaped(out, config.name)?;
+    out.write_str("\",\"playlist\":{\"ps\":[")?;
+    write_list(out, playable().enumerate().map(|(n, _)| first + n))?;
+    out.write_str("],\"dur\":[")?;
+    write_list(
+        out,
+        playable().map(|(i, a)| tenths(i, one_pass_ms(a), &mut warn)),
+    )?;
+    out.write_str("],\"transition\":[")?;
+    write_list(
+        out,
+        playable().map(|(i, a)| Preset::from_action(i, a, &mut |_| {}).transition_tenths),
+    )?;
+
+    // If an action loops forever, the playlist ends by applying that
+    // action's preset, which then stays active
+    let (repeat, end) = match (forever, behavior) {
+        (Some(_), _) => (1, first + count - 1),
+        (None, LoopBehavior::LoopForever) => (0, 0),
+        (None, LoopBehavior::LoopN { cycles, .. }) => (cycles.saturating_add(1), 0),
+        (None, _) => (1, 0),
+    };
+    write!(out, "],\"repeat\":{},\"end\":{}}}}}}}", repeat, end)?;
+
+    Ok(())
+}
+
+/// The WLED settings used to approximate a single action
+struct Preset {
+    fx: u8,
+    sx: u8,
+    color: RGB8,
+    transition_tenths: u32,
+}
+
+impl Preset {
+    fn from_action<R, F>(index: usize, action: &Action<R>, warn: &mut F) -> Self
+    where
+        R: RollingTimer<Tick = u32> + Default + Clone,
+        F: FnMut(Warning),
+    {
+        let mut preset = Preset {
+            fx: FX_SOLID,
+            sx: 128,
+            color: action.color,
+            transition_tenths: 0,
+        };
+
+        // Transitions take up the entire duration of the action
+        let transition = round_tenths(action.duration_ms).min(MAX_TENTHS);
+
+        match action.kind() {
+            InnerActionKind::Static(_) => {}
+            InnerActionKind::Seek(_) => preset.transition_tenths = transition,
+            InnerActionKind::Fade(f) => {
+                warn(Warning::ApproximateEffect { action: index });
+                if !f.is_fade_up() {
+                    preset.color = BLACK;
+                }
+                preset.transition_tenths = transition;
+            }
+            InnerActionKind::Sin(_) => {
+                warn(Warning::ApproximateEffect { action: index });
+                if action.phase_offset_ms != 0 {
+                    warn(Warning::PhaseOffsetIgnored { action: index });
+                }
+
+                // WLED's breathe effect has a period of roughly
+                // 65536 / ((speed >> 3) + 10) milliseconds
+                let period = action.period_ms.max(1.0);
+                let step = (65536.0 / period) - 10.0;
+                preset.fx = FX_BREATHE;
+                preset.sx = (step.clamp(0.0, 31.0) as u8) << 3;
+            }
+        }
+
+        preset
+    }
+}
+
+/// The time an action plays for, counting only one pass of actions
+/// that loop forever
+fn one_pass_ms<R>(action: &Action<R>) -> u32
+where
+    R: RollingTimer<Tick = u32> + Default + Clone,
+{
+    match action.play_time().as_ms() {
+        Some(ms) => ms.min(u32::MAX as u64) as u32,
+        None => action.duration_ms,
+    }
+}
+
+/// Convert milliseconds to the tenths of a second used by WLED,
+/// warning if this is not exact
+fn tenths<F>(index: usize, ms: u32, warn: &mut F) -> u32
+where
+    F: FnMut(Warning),
+{
+    let tenths = round_tenths(ms).clamp(1, MAX_TENTHS);
+    if (tenths as u64) * 100 != (ms as u64) {
+        warn(Warning::DurationRounded { action: index });
+    }
+    tenths
+}
+
+/// Round milliseconds to the nearest tenth of a second
+fn round_tenths(ms: u32) -> u32 {
+    ((ms as u64 + 50) / 100) as u32
+}
+
+fn write_list<W, I, T>(out: &mut W, items: I) -> fmt::Result
+where
+    W: Write,
+    I: Iterator<Item = T>,
+    T: fmt::Display,
+{
+    for (i, item) in items.enumerate() {
+        if i != 0 {
+            out.write_char(',')?;
+        }
+        write!(out, "{}", item)?;
+    }
+    Ok(())
+}
+
+fn write_escaped<W: Write>(out: &mut W, s: &str) -> fmt::Result {
+    for c in s.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export, Export, ExportError, Warning};
+    use crate::engine::{Action, LoopBehavior};
+    use crate::script;
+    use groundhog::std_timer::Timer;
+
+    type MicroTimer = Timer<1_000_000>;
+
+    #[test]
+    fn playlist() {
+        let actions: [Action<MicroTimer>; 3] = script! {
+            | action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+            |  solid |    RED |        1000 |         0.0 |               0 |   once |
+            |   seek |   BLUE |         450 |         0.0 |               0 |   once |
+            |    sin |  WHITE |        4000 |      4000.0 |               0 |   once |
+        };
+
+        let mut json = String::new();
+        let mut warnings = Vec::new();
+        export(
+            &Export::new("demo", 0, 30),
+            &actions,
+            &LoopBehavior::LoopForever,
+            &mut json,
+            |w| warnings.push(w),
+        )
+        .unwrap();
+
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"1":{"n":"demo 0","on":true,"seg":[{"id":0,"start":0,"stop":30,"fx":0,"sx":128,"col":[[255,0,0]]}]},"#,
+                r#""2":{"n":"demo 1","on":true,"seg":[{"id":0,"start":0,"stop":30,"fx":0,"sx":128,"col":[[0,0,255]]}]},"#,
+                r#""3":{"n":"demo 2","on":true,"seg":[{"id":0,"start":0,"stop":30,"fx":2,"sx":48,"col":[[255,255,255]]}]},"#,
+                r#""4":{"n":"demo","playlist":{"ps":[1,2,3],"dur":[10,5,40],"transition":[0,5,0],"repeat":0,"end":0}}}"#,
+            )
+        );
+        assert_eq!(
+            warnings,
+            [
+                Warning::ApproximateEffect { action: 2 },
+                Warning::DurationRounded { action: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn long_durations() {
+        let actions: [Action<MicroTimer>; 2] = [
+            Action::build()
+                .seek()
+                .color(crate::colors::RED)
+                .for_ms(u32::MAX)
+                .once()
+                .finish(),
+            Action::build()
+                .solid()
+                .color(crate::colors::BLUE)
+                .for_ms(5000)
+                .times(1_000_000)
+                .finish(),
+        ];
+
+        let mut json = String::new();
+        let mut warnings = Vec::new();
+        export(
+            &Export::new("long", 0, 1),
+            &actions,
+            &LoopBehavior::OneShot,
+            &mut json,
+            |w| warnings.push(w),
+        )
+        .unwrap();
+
+        assert!(json.contains(r#""dur":[65535,65535],"transition":[65535,0]"#));
+        assert_eq!(
+            warnings,
+            [
+                Warning::DurationRounded { action: 0 },
+                Warning::DurationRounded { action: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_first_preset() {
+        let actions: [Action<MicroTimer>; 1] = script! {
+            | action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+            |  solid |    RED |        1000 |         0.0 |               0 |   once |
+        };
+        let mut config = Export::new("demo", 0, 30);
+        let mut json = String::new();
+
+        config.first_preset = 0;
+        assert_eq!(
+            export(&config, &actions, &LoopBehavior::OneShot, &mut json, |_| {}),
+            Err(ExportError::InvalidFirstPreset)
+        );
+
+        config.first_preset = 250;
+        assert_eq!(
+            export(&config, &actions, &LoopBehavior::OneShot, &mut json, |_| {}),
+            Err(ExportError::TooManyPresets)
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//!
+//! Zones give names to groups of LEDs, so that application code
+//! can refer to "the ring" or "the status LEDs", rather than to
+//! specific LED indexes.
+//!
+//! Zones are typically defined once, for example as a `const`
+//! table that matches the wiring of the hardware. Everything else
+//! can then use the zone name, and only the table needs to change
+//! if the wiring does.
+//!
+//! A [`Zone`] can select the matching items from any slice that
+//! has one item per LED, such as an array of [`Sequence`]s, a frame
+//! of colors, or the levels of a [`TrimTable`].
+//!
+//! [`Zone`]: crate::zones::Zone
+//! [`Sequence`]: crate::engine::Sequence
+//! [`TrimTable`]: crate::correction::TrimTable
+
+/// The LEDs that belong to a [`Zone`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Members<'a> {
+    /// A contiguous range of LEDs, from `start` up to (but
+    /// not including) `end`
+    Range {
+        /// The first LED of the range
+        start: usize,
+
+        /// One past the last LED of the range
+        end: usize,
+    },
+
+    /// An arbitrary list of LEDs
+    List(&'a [usize]),
+}
+
+/// A named group of LEDs
+///
+/// # Example
+///
+/// ```rust
+/// use choreographer::{
+///     engine::{LoopBehavior, Sequence},
+///     script,
+///     zones::{Zone, Zones},
+/// };
+/// # use groundhog::std_timer::Timer;
+/// # type MicroTimer = Timer<1_000_000>;
+///
+/// const ZONES: Zones = Zones::new(&[
+///     Zone::range("ring", 0, 12),
+///     Zone::list("status", &[12, 15]),
+/// ]);
+///
+/// let mut leds: [Sequence<MicroTimer, 8>; 16] = Sequence::new_array();
+///
+/// let ring = ZONES.get("ring").unwrap();
+/// for led in ring.select_mut(&mut leds) {
+///     led.set(&script!(
+///         | action |  color | duration_ms | period_ms_f | phase_offset_ms | repeat |
+///         |    sin |  WHITE |        2500 |      2500.0 |               0 |   once |
+///     ), LoopBehavior::LoopForever);
+/// }
+///
+/// assert!(leds[11].poll().is_some());
+/// assert!(leds[12].poll().is_none());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Zone<'a> {
+    name: &'a str,
+    members: Members<'a>,
+}
+
+impl<'a> Zone<'a> {
+    /// Create a new zone with the given members
+    pub const fn new(name: &'a str, members: Members<'a>) -> Self {
+        Self { name, members }
+    }
+
+    /// Create a new zone from a contiguous range of LEDs, from
+    /// `start` up to (but not including) `end`
+    pub const fn range(name: &'a str, start: usize, end: usize) -> Self {
+        Self::new(name, Members::Range { start, end })
+    }
+
+    /// Create a new zone from a list of LEDs
+    pub const fn list(name: &'a str, leds: &'a [usize]) -> Self {
+        Self::new(name, Members::List(leds))
+    }
+
+    /// The name of this zone
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// The LEDs that belong to this zone
+    pub fn members(&self) -> Members<'a> {
+        self.members
+    }
+
+    /// Does the given LED belong to this zone?
+    pub fn contains(&self, index: usize) -> bool {
+        match self.members {
+            Members::Range { start, end } => (start..end).contains(&index),
+            Members::List(leds) => leds.contains(&index),
+        }
+    }
+
+    /// Iterate over the indexes of all LEDs in this zone
+    pub fn indices(&self) -> impl Iterator<Item = usize> + 'a {
+        let (range, list) = match self.members {
+            Members::Range { start, end } => (start..end, &[][..]),
+            Members::List(leds) => (0..0, leds),
+        };
+        range.chain(list.iter().copied())
+    }
+
+    /// Select the items of a per-LED slice that belong to this zone
+    ///
+    /// Zone members that are outside of `items` are skipped. Items
+    /// are yielded in LED order, and each item is yielded at most
+    /// once, even if it is listed more than once.
+    pub fn select_mut<'b, T>(&'b self, items: &'b mut [T]) -> impl Iterator<Item = &'b mut T> {
+        items
+            .iter_mut()
+            .enumerate()
+            .filter(move |(i, _)| self.contains(*i))
+            .map(|(_, item)| item)
+    }
+}
+
+/// A table of named [`Zone`]s
+///
+/// Zones may overlap, in which case an LED belongs to all of
+/// the zones that contain it.
+#[derive(Clone, Copy, Debug)]
+pub struct Zones<'a> {
+    zones: &'a [Zone<'a>],
+}
+
+impl<'a> Zones<'a> {
+    /// Create a new table of zones
+    pub const fn new(zones: &'a [Zone<'a>]) -> Self {
+        Self { zones }
+    }
+
+    /// Find a zone by name
+    ///
+    /// If more than one zone has the same name, the first
+    /// is returned.
+    pub fn get(&self, name: &str) -> Option<&'a Zone<'a>> {
+        self.zones.iter().find(|z| z.name == name)
+    }
+
+    /// Iterate over all zones
+    pub fn iter(&self) -> impl Iterator<Item = &'a Zone<'a>> {
+        self.zones.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Members, Zone, Zones};
+    use std::vec::Vec;
+
+    #[test]
+    fn members() {
+        let ring = Zone::range("ring", 2, 5);
+        assert!(!ring.contains(1));
+        assert!(ring.contains(2));
+        assert!(ring.contains(4));
+        assert!(!ring.contains(5));
+        assert_eq!(ring.indices().collect::<Vec<_>>(), [2, 3, 4]);
+
+        let status = Zone::list("status", &[7, 3, 7]);
+        assert!(status.contains(3));
+        assert!(status.contains(7));
+        assert!(!status.contains(4));
+        assert_eq!(status.indices().collect::<Vec<_>>(), [7, 3, 7]);
+        assert_eq!(status.members(), Members::List(&[7, 3, 7]));
+
+        let empty = Zone::range("empty", 3, 3);
+        assert!(!empty.contains(3));
+        assert_eq!(empty.indices().count(), 0);
+    }
+
+    #[test]
+    fn select_mut() {
+        let mut items = [0, 1, 2, 3, 4];
+
+        // Out of range members are skipped, duplicates are only
+        // yielded once, and items come out in LED order
+        let zone = Zone::list("status", &[9, 3, 1, 3]);
+        assert_eq!(
+            zone.select_mut(&mut items).map(|i| *i).collect::<Vec<_>>(),
+            [1, 3]
+        );
+
+        for item in zone.select_mut(&mut items) {
+            *item += 10;
+        }
+        assert_eq!(items, [0, 11, 2, 13, 4]);
+
+        let zone = Zone::range("tail", 3, 8);
+        assert_eq!(
+            zone.select_mut(&mut items).map(|i| *i).collect::<Vec<_>>(),
+            [13, 4]
+        );
+    }
+
+    #[test]
+    fn lookup() {
+        const ZONES: Zones = Zones::new(&[
+            Zone::range("ring", 0, 12),
+            Zone::list("status", &[12, 15]),
+            Zone::range("ring", 20, 24),
+        ]);
+
+        assert_eq!(ZONES.get("ring"), Some(&Zone::range("ring", 0, 12)));
+        assert_eq!(ZONES.get("status").map(Zone::name), Some("status"));
+        assert_eq!(ZONES.get("missing"), None);
+        assert_eq!(ZONES.iter().count(), 3);
+    }
+}